@@ -1,12 +1,23 @@
 use std::ffi::OsStr;
-use std::fs;
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::str::FromStr;
 
 use anyhow::{bail, Context, Result};
 pub use cargo_metadata as metadata;
+use serde::{Deserialize, Serialize};
 use toml_edit as toml;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// The name of the directory, relative to the workspace root, that holds the
+/// workflow's static assets (`info.plist`, icons, …).
+const WORKFLOW_DIR: &str = "workflow";
+
+/// The unix permission bits applied to the workflow's executable(s) once
+/// they're staged in the archive, so Alfred can run them after import.
+const EXECUTABLE_MODE: u32 = 0o755;
 
 #[derive(Debug)]
 pub struct Cargo {
@@ -50,17 +61,33 @@ impl Mode {
 }
 
 /// Run a `cargo init` command.
+///
+/// After `cargo init` succeeds, if the package's manifest has a
+/// `[package.metadata.powerpack]` table, this also generates `info.plist`.
+/// A freshly scaffolded manifest has no such table, so this step normally
+/// does nothing. `cargo init` fails on a directory that already holds a Cargo
+/// package, so running this again does not regenerate `info.plist`.
 pub fn init<P, N>(path: P, name: Option<N>) -> Result<()>
 where
     P: AsRef<OsStr>,
     N: AsRef<OsStr>,
 {
+    let dir = PathBuf::from(path.as_ref());
+
     let mut cmd = Cargo::new("init");
     if let Some(name) = name {
         cmd.arg("--name").arg(name);
     }
     cmd.arg("--bin").arg(path);
-    cmd.run()
+    cmd.run()?;
+
+    if let Some((workflow_metadata, binaries, package_name)) = read_workflow_metadata_at(&dir)? {
+        let workflow_dir = dir.join(WORKFLOW_DIR);
+        fs::create_dir_all(&workflow_dir)?;
+        write_info_plist(&workflow_dir, &workflow_metadata, &binaries, &package_name)?;
+    }
+
+    Ok(())
 }
 
 /// Run a `cargo build` command.
@@ -118,3 +145,307 @@ pub fn package_name() -> Result<String> {
     let package = metadata.root_package().context("no root package")?;
     Ok(package.name.clone())
 }
+
+/// The `[package.metadata.powerpack]` table, describing the Alfred workflow
+/// that this package produces.
+///
+/// See <https://www.alfredapp.com/help/workflows/> for the meaning of these
+/// fields in terms of the workflow they end up configuring.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkflowMetadata {
+    /// The workflow's bundle identifier, e.g. `com.example.my-workflow`.
+    pub bundle_id: String,
+
+    /// The human-readable workflow name. Defaults to the package name.
+    pub name: Option<String>,
+
+    /// A short description of what the workflow does.
+    pub description: Option<String>,
+
+    /// The workflow author, shown in Alfred's workflow list.
+    pub author: Option<String>,
+
+    /// Markdown readme text, shown in Alfred's workflow editor.
+    pub readme: Option<String>,
+
+    /// Path to the workflow icon (a PNG), relative to the workflow
+    /// directory. Copied into the bundle as `icon.png`.
+    pub icon: Option<String>,
+
+    /// The workflow's script filters.
+    #[serde(default)]
+    pub script_filters: Vec<ScriptFilterDef>,
+}
+
+/// A single script-filter input object, describing a keyword that invokes
+/// one of the package's binaries.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptFilterDef {
+    /// The keyword that triggers this script filter.
+    pub keyword: String,
+
+    /// A short description, shown in Alfred's workflow editor.
+    pub description: Option<String>,
+
+    /// Name of the binary target this script filter invokes.
+    ///
+    /// Defaults to the package's only binary target; required if the
+    /// package builds more than one.
+    pub binary: Option<String>,
+}
+
+/// Read the `[package.metadata.powerpack]` table for the package whose
+/// manifest lives in `dir`, along with its binary target names and package
+/// name, or `None` if the table hasn't been added yet (e.g. right after
+/// `cargo init`).
+fn read_workflow_metadata_at(dir: &Path) -> Result<Option<(WorkflowMetadata, Vec<String>, String)>> {
+    let metadata = metadata::MetadataCommand::new()
+        .manifest_path(dir.join("Cargo.toml"))
+        .exec()?;
+    let package = metadata.root_package().context("no root package")?;
+    let Some(powerpack) = package.metadata.get("powerpack") else {
+        return Ok(None);
+    };
+    let workflow_metadata: WorkflowMetadata =
+        serde_json::from_value(powerpack.clone()).context("invalid [package.metadata.powerpack] table")?;
+    let binaries = package
+        .targets
+        .iter()
+        .filter(|target| target.kind.iter().any(|kind| kind == "bin"))
+        .map(|target| target.name.clone())
+        .collect();
+    Ok(Some((workflow_metadata, binaries, package.name.clone())))
+}
+
+/// Read the `[package.metadata.powerpack]` table for the workspace's root
+/// package.
+pub fn read_workflow_metadata() -> Result<WorkflowMetadata> {
+    let metadata = metadata::MetadataCommand::new().exec()?;
+    let package = metadata.root_package().context("no root package")?;
+    let powerpack = package
+        .metadata
+        .get("powerpack")
+        .context("missing [package.metadata.powerpack] table")?;
+    serde_json::from_value(powerpack.clone()).context("invalid [package.metadata.powerpack] table")
+}
+
+/// The top-level Alfred `info.plist` document.
+///
+/// This only models the subset of the format that powerpack generates; any
+/// fields a user added by hand to an existing `info.plist` before adopting
+/// this generator are not preserved.
+#[derive(Debug, Clone, Serialize)]
+struct InfoPlist {
+    bundleid: String,
+    name: String,
+    createdby: String,
+    description: String,
+    readme: String,
+    webaddress: String,
+    objects: Vec<PlistObject>,
+    connections: std::collections::HashMap<String, Vec<()>>,
+    uidata: std::collections::HashMap<String, ()>,
+}
+
+/// A script-filter input object, wired up to run one of the workflow's
+/// binaries.
+#[derive(Debug, Clone, Serialize)]
+struct PlistObject {
+    uid: String,
+    #[serde(rename = "type")]
+    kind: String,
+    config: ScriptFilterConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ScriptFilterConfig {
+    keyword: String,
+    scriptfile: String,
+    /// `8` is Alfred's "External Script File" script type; it runs
+    /// `scriptfile` directly instead of the (empty, unused here) inline
+    /// `script` string that type `0` ("/bin/bash") would execute.
+    #[serde(rename = "type")]
+    script_type: u32,
+    /// `1` passes the script filter's query to the binary as `argv[1]`.
+    scriptargtype: u32,
+    withspace: bool,
+    /// Shown in Alfred's workflow editor underneath the keyword.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subtext: Option<String>,
+}
+
+/// Generate and write the workflow's `info.plist` from `metadata`.
+///
+/// `binaries` is the package's binary target names, used to resolve each
+/// script filter's `binary` (or to default it when the package only builds
+/// one). `package_name` is used as the workflow name when `metadata.name` is
+/// not set.
+pub fn write_info_plist(
+    dir: &Path,
+    metadata: &WorkflowMetadata,
+    binaries: &[String],
+    package_name: &str,
+) -> Result<()> {
+    let objects = metadata
+        .script_filters
+        .iter()
+        .map(|filter| {
+            let binary = match &filter.binary {
+                Some(binary) => binary.clone(),
+                None => match binaries {
+                    [only] => only.clone(),
+                    _ => bail!(
+                        "script filter `{}` must set `binary` because the package does not have exactly one binary target",
+                        filter.keyword
+                    ),
+                },
+            };
+            Ok(PlistObject {
+                uid: format!("powerpack-script-filter-{}", filter.keyword),
+                kind: "alfred.workflow.input.scriptfilter".to_owned(),
+                config: ScriptFilterConfig {
+                    keyword: filter.keyword.clone(),
+                    scriptfile: binary,
+                    script_type: 8,
+                    scriptargtype: 1,
+                    withspace: true,
+                    subtext: filter.description.clone(),
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let plist = InfoPlist {
+        bundleid: metadata.bundle_id.clone(),
+        name: metadata.name.clone().unwrap_or_else(|| package_name.to_owned()),
+        createdby: metadata.author.clone().unwrap_or_default(),
+        description: metadata.description.clone().unwrap_or_default(),
+        readme: metadata.readme.clone().unwrap_or_default(),
+        webaddress: String::new(),
+        objects,
+        connections: std::collections::HashMap::new(),
+        uidata: std::collections::HashMap::new(),
+    };
+
+    let path = dir.join("info.plist");
+    let file = File::create(&path).with_context(|| format!("failed to create `{}`", path.display()))?;
+    plist::to_writer_xml(file, &plist).context("failed to serialize info.plist")?;
+
+    if let Some(icon) = &metadata.icon {
+        let source = dir.join(icon);
+        let dest = dir.join("icon.png");
+        if source != dest {
+            fs::copy(&source, &dest)
+                .with_context(|| format!("failed to copy icon from `{}`", source.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the package in `mode` and bundle it into a distributable
+/// `.alfredworkflow` archive in the workspace root.
+///
+/// The archive holds `info.plist` and any other files in the
+/// [`WORKFLOW_DIR`] directory, plus the compiled binaries, which are read
+/// from `mode`'s target directory and added at the archive root. An
+/// `.alfredworkflow` file is just a zip archive that Alfred unpacks on
+/// import, so the resulting file can be shared or double-clicked to install.
+pub fn package(mode: Mode) -> Result<()> {
+    build(mode)?;
+
+    let workspace = workspace_directory()?;
+    let target = target_directory()?;
+    let name = package_name()?;
+
+    let workflow_metadata = read_workflow_metadata()?;
+    let workflow_dir = workspace.join(WORKFLOW_DIR);
+    fs::create_dir_all(&workflow_dir)?;
+    let binaries = binary_names()?;
+    write_info_plist(&workflow_dir, &workflow_metadata, &binaries, &name)?;
+
+    let archive_path = workspace.join(format!("{}.alfredworkflow", name));
+    let file = File::create(&archive_path)
+        .with_context(|| format!("failed to create `{}`", archive_path.display()))?;
+    let mut zip = ZipWriter::new(file);
+
+    add_assets(&mut zip, &workspace.join(WORKFLOW_DIR))?;
+    add_binaries(&mut zip, &target.join(mode.dir()), &binaries)?;
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// Recursively add every file under `dir` to `zip`, skipping any nested
+/// `target` directory (e.g. from a workflow that vendors its own crate).
+fn add_assets<W: std::io::Write + std::io::Seek>(zip: &mut ZipWriter<W>, dir: &Path) -> Result<()> {
+    add_dir(zip, dir, dir)
+}
+
+fn add_dir<W: std::io::Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    root: &Path,
+    dir: &Path,
+) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    // `fs::read_dir`'s order is platform-dependent; sort so the archive's
+    // entry order is deterministic across runs and machines.
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            if path.file_name() == Some(OsStr::new("target")) {
+                continue;
+            }
+            add_dir(zip, root, &path)?;
+        } else {
+            add_file(zip, root, &path, None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Add every binary in `binaries` from `bin_dir` to `zip`, preserving the
+/// executable permission bit so the workflow runs after import.
+fn add_binaries<W: std::io::Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    bin_dir: &Path,
+    binaries: &[String],
+) -> Result<()> {
+    for binary in binaries {
+        let path = bin_dir.join(binary);
+        add_file(zip, bin_dir, &path, Some(EXECUTABLE_MODE))?;
+    }
+    Ok(())
+}
+
+/// Add a single file to `zip` under its path relative to `root`, using `/` as
+/// the separator so the archive is portable regardless of platform.
+fn add_file<W: std::io::Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    root: &Path,
+    path: &Path,
+    mode: Option<u32>,
+) -> Result<()> {
+    let name = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut options = FileOptions::default();
+    if let Some(mode) = mode {
+        options = options.unix_permissions(mode);
+    }
+
+    zip.start_file(name, options)?;
+    let contents = fs::read(path).with_context(|| format!("failed to read `{}`", path.display()))?;
+    std::io::Write::write_all(zip, &contents)?;
+    Ok(())
+}
@@ -0,0 +1,438 @@
+//! Typed deserialization of the process environment, including Alfred's
+//! user-defined "Workflow Configuration" variables.
+//!
+//! Alfred injects every configuration variable a user has set up for a
+//! workflow as a plain process environment variable. Today that means
+//! authors either hand-roll [`std::env::var`] calls or fall back to the
+//! stringly-typed [`crate::var`] helper for each one. [`config`] instead
+//! treats the environment as a map and deserializes it straight into a
+//! user-defined type.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     api_token: String,
+//!     max_results: u32,
+//! }
+//!
+//! # fn main() -> Result<(), powerpack_env::config::Error> {
+//! let config: Config = powerpack_env::config::config()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, Visitor};
+
+/// Deserialize `T` from the process environment.
+///
+/// Field names are matched against environment variable names
+/// case-insensitively. An absent or empty variable is treated the same way
+/// [`crate::var`] treats it: the field is omitted entirely, so an
+/// `Option<T>` field deserializes as `None`, a field with `#[serde(default)]`
+/// takes its default, and any other field is a "missing field" error.
+pub fn config<T: DeserializeOwned>() -> Result<T, Error> {
+    config_with_prefix(None)
+}
+
+/// Like [`config`], but only considers environment variables whose name
+/// starts with `prefix` (matched case-insensitively), stripping the prefix
+/// before matching the remainder against field names.
+pub fn config_with_prefix<T: DeserializeOwned>(prefix: Option<&str>) -> Result<T, Error> {
+    T::deserialize(EnvDeserializer::new(prefix))
+}
+
+/// An error encountered while deserializing the process environment.
+#[derive(Debug)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// A lowercased view of the process environment, optionally restricted to a
+/// prefix, used to resolve struct fields by name.
+struct EnvDeserializer {
+    vars: HashMap<String, OsString>,
+}
+
+impl EnvDeserializer {
+    fn new(prefix: Option<&str>) -> Self {
+        let prefix = prefix.map(str::to_lowercase);
+        let vars = std::env::vars_os()
+            .filter_map(|(key, value)| {
+                let key = key.to_str()?.to_lowercase();
+                let key = match &prefix {
+                    Some(prefix) => key.strip_prefix(prefix.as_str())?.to_owned(),
+                    None => key,
+                };
+                Some((key, value))
+            })
+            .collect();
+        Self { vars }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for EnvDeserializer {
+    type Error = Error;
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(FieldMapAccess {
+            fields: fields.iter(),
+            vars: &self.vars,
+            current: None,
+        })
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Error(
+            "env::config can only deserialize into a struct".to_owned(),
+        ))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+struct FieldMapAccess<'a> {
+    fields: std::slice::Iter<'static, &'static str>,
+    vars: &'a HashMap<String, OsString>,
+    current: Option<(&'static str, &'a str)>,
+}
+
+impl FieldMapAccess<'_> {
+    /// Look up `field` in the environment, treating an absent or empty
+    /// variable as "not set", the same way [`crate::var`] does.
+    fn value_of(&self, field: &str) -> Option<&str> {
+        self.vars
+            .get(&field.to_lowercase())
+            .and_then(|v| v.to_str())
+            .filter(|s| !s.is_empty())
+    }
+}
+
+impl<'de> MapAccess<'de> for FieldMapAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        // Only surface fields whose variable is actually set. A field that's
+        // missing from the map entirely (rather than present with a `None`
+        // value) lets serde apply `#[serde(default)]`, or its own implicit
+        // `None` default for `Option<T>` fields, instead of us having to
+        // reimplement that here.
+        for &field in self.fields.by_ref() {
+            if let Some(value) = self.value_of(field) {
+                self.current = Some((field, value));
+                return seed.deserialize(de::value::StrDeserializer::new(field)).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (field, value) = self
+            .current
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(ValueDeserializer { field, value })
+    }
+}
+
+/// Deserializes a single environment variable, coercing it into the type the
+/// target field asks for.
+struct ValueDeserializer<'a> {
+    field: &'static str,
+    value: &'a str,
+}
+
+macro_rules! deserialize_parsed {
+    ($($deserialize:ident => $visit:ident : $ty:ty),* $(,)?) => {
+        $(
+            fn $deserialize<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let parsed: $ty = self.value.parse().map_err(|_| {
+                    Error(format!(
+                        "environment variable for field `{}` is `{}`, which is not a valid {}",
+                        self.field,
+                        self.value,
+                        stringify!($ty)
+                    ))
+                })?;
+                visitor.$visit(parsed)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_str(self.value)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // `FieldMapAccess` only yields fields whose variable is actually
+        // set, so by the time we get here there's always a value.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            "1" | "true" | "yes" => visitor.visit_bool(true),
+            "0" | "false" | "no" => visitor.visit_bool(false),
+            other => Err(Error(format!(
+                "environment variable for field `{}` is `{other}`, which is not a valid bool",
+                self.field
+            ))),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_str(self.value)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_string(self.value.to_owned())
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+        deserialize_char => visit_char: char,
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde::Deserialize;
+
+    use super::{config, config_with_prefix};
+
+    // `std::env` is process-global, so serialize every test that touches it
+    // to keep them from clobbering each other's variables.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Sets environment variables for the duration of the guard, then
+    /// removes them, so a panicking assertion can't leak state into other
+    /// tests.
+    struct EnvGuard<'a>(Vec<&'a str>);
+
+    impl<'a> EnvGuard<'a> {
+        fn set(vars: &[(&'a str, &str)]) -> Self {
+            for (key, value) in vars {
+                std::env::set_var(key, value);
+            }
+            Self(vars.iter().map(|(key, _)| *key).collect())
+        }
+    }
+
+    impl Drop for EnvGuard<'_> {
+        fn drop(&mut self) {
+            for key in &self.0 {
+                std::env::remove_var(key);
+            }
+        }
+    }
+
+    #[test]
+    fn deserializes_strings_and_numbers() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            powerpack_test_api_token: String,
+            powerpack_test_max_results: u32,
+        }
+
+        let _env = EnvGuard::set(&[
+            ("POWERPACK_TEST_API_TOKEN", "secret"),
+            ("POWERPACK_TEST_MAX_RESULTS", "42"),
+        ]);
+
+        let cfg: Config = config().unwrap();
+        assert_eq!(cfg.powerpack_test_api_token, "secret");
+        assert_eq!(cfg.powerpack_test_max_results, 42);
+    }
+
+    #[test]
+    fn rejects_an_unparsable_number() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            powerpack_test_max_results: u32,
+        }
+
+        let _env = EnvGuard::set(&[("POWERPACK_TEST_MAX_RESULTS", "not a number")]);
+
+        let err = config::<Config>().unwrap_err();
+        assert!(err.to_string().contains("powerpack_test_max_results"));
+    }
+
+    #[test]
+    fn coerces_bools() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            powerpack_test_flag: bool,
+        }
+
+        for (value, expected) in [("1", true), ("true", true), ("yes", true), ("0", false), ("false", false), ("no", false)] {
+            let _env = EnvGuard::set(&[("POWERPACK_TEST_FLAG", value)]);
+            let cfg: Config = config().unwrap();
+            assert_eq!(cfg.powerpack_test_flag, expected, "for input {value:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_an_invalid_bool() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            powerpack_test_flag: bool,
+        }
+
+        let _env = EnvGuard::set(&[("POWERPACK_TEST_FLAG", "maybe")]);
+        assert!(config::<Config>().is_err());
+    }
+
+    #[test]
+    fn absent_or_empty_variable_is_none_for_option() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            powerpack_test_optional: Option<String>,
+        }
+
+        // Not set at all.
+        let cfg: Config = config().unwrap();
+        assert_eq!(cfg.powerpack_test_optional, None);
+
+        // Set, but empty.
+        let _env = EnvGuard::set(&[("POWERPACK_TEST_OPTIONAL", "")]);
+        let cfg: Config = config().unwrap();
+        assert_eq!(cfg.powerpack_test_optional, None);
+    }
+
+    #[test]
+    fn present_variable_is_some_for_option() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            powerpack_test_optional: Option<String>,
+        }
+
+        let _env = EnvGuard::set(&[("POWERPACK_TEST_OPTIONAL", "value")]);
+        let cfg: Config = config().unwrap();
+        assert_eq!(cfg.powerpack_test_optional, Some("value".to_owned()));
+    }
+
+    #[test]
+    fn absent_variable_takes_the_serde_default() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(default)]
+            powerpack_test_retries: u32,
+        }
+
+        let cfg: Config = config().unwrap();
+        assert_eq!(cfg.powerpack_test_retries, 0);
+    }
+
+    #[test]
+    fn missing_required_field_names_the_field() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            powerpack_test_required: String,
+        }
+
+        std::env::remove_var("POWERPACK_TEST_REQUIRED");
+        let err = config::<Config>().unwrap_err();
+        assert!(err.to_string().contains("powerpack_test_required"));
+    }
+
+    #[test]
+    fn prefix_strips_and_matches_case_insensitively() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        #[derive(Deserialize)]
+        struct Config {
+            api_token: String,
+        }
+
+        let _env = EnvGuard::set(&[("MYAPP_API_TOKEN", "prefixed")]);
+        let cfg: Config = config_with_prefix(Some("myapp_")).unwrap();
+        assert_eq!(cfg.api_token, "prefixed");
+    }
+}
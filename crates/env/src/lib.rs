@@ -2,6 +2,9 @@
 //!
 //! See <https://www.alfredapp.com/help/workflows/script-environment-variables/>
 
+pub mod config;
+pub mod logger;
+
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
@@ -0,0 +1,55 @@
+//! A [`log::Log`] backend that routes diagnostics into Alfred's debug panel.
+//!
+//! Alfred surfaces a script filter's stderr output in its debugger window, so
+//! this backend formats each record and writes it there, gating anything
+//! more verbose than [`log::Level::Error`] behind [`crate::is_debug`] so
+//! release runs of a workflow stay quiet.
+
+use std::io::Write;
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::is_debug;
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= LevelFilter::Error || is_debug()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let _ = writeln!(
+            std::io::stderr(),
+            "[{level}] {target}: {args}",
+            level = record.level(),
+            target = record.target(),
+            args = record.args(),
+        );
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Install this crate's [`log::Log`] implementation as the global logger.
+///
+/// Only [`log::Level::Error`] and more severe records are emitted unless the
+/// Alfred debug panel is open (see [`is_debug`]), so a workflow author can
+/// use the qualified `log::debug!`/`log::error!` macros and get useful
+/// output while debugging without spamming stderr during normal use.
+///
+/// # Errors
+///
+/// Returns an error if a global logger has already been set.
+pub fn init() -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
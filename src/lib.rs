@@ -174,13 +174,28 @@ pub struct Item<'a> {
     /// A Quick Look URL which will be shown if the user uses Quick Look (⌘+Y).
     #[serde(rename = "quicklookurl", skip_serializing_if = "Option::is_none")]
     quicklook_url: Option<String<'a>>,
+
+    /// Variables to pass out of the workflow when this item is actioned.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    variables: HashMap<String<'a>, String<'a>>,
 }
 
 /// The output of a workflow (i.e. input for the script filter)
-#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct Output<'a> {
     /// Each row item.
     items: Vec<Item<'a>>,
+
+    /// Variables to pass out of the script filter to downstream workflow
+    /// objects.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    variables: HashMap<String<'a>, String<'a>>,
+
+    /// Tells Alfred to re-run the script filter after the given number of
+    /// seconds (valid range is roughly `0.1` to `5.0`), useful for progress
+    /// indicators or polling workflows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rerun: Option<f64>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -347,6 +362,27 @@ impl<'a> Item<'a> {
         self.modifiers.insert(key, data);
         self
     }
+
+    /// Set a single variable to be passed out of the workflow when this item
+    /// is actioned.
+    #[must_use]
+    pub fn variable(mut self, key: impl Into<String<'a>>, value: impl Into<String<'a>>) -> Self {
+        self.variables.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the variables to be passed out of the workflow when this item is
+    /// actioned.
+    #[must_use]
+    pub fn variables<I, K, V>(mut self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String<'a>>,
+        V: Into<String<'a>>,
+    {
+        self.variables = iter.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        self
+    }
 }
 
 impl<'a> Output<'a> {
@@ -359,6 +395,27 @@ impl<'a> Output<'a> {
         self
     }
 
+    /// Set a single variable to be exported to downstream workflow objects.
+    #[must_use]
+    pub fn variable(mut self, key: impl Into<String<'a>>, value: impl Into<String<'a>>) -> Self {
+        self.variables.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the variables to be exported to downstream workflow objects.
+    #[must_use]
+    pub fn variables<I, K, V>(mut self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String<'a>>,
+        V: Into<String<'a>>,
+    {
+        self.variables = iter.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        self
+    }
+
+    setter! { rerun, Option<f64> }
+
     pub fn write<W: io::Write>(&self, w: W) -> serde_json::Result<()> {
         serde_json::to_writer(w, self)
     }